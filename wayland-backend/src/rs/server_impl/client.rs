@@ -265,6 +265,30 @@ impl<D> Client<D> {
         message: CString,
     ) {
         let converted_message = message.to_string_lossy().into();
+        self.send_error(object_id.clone(), error_code, message);
+        self.kill(DisconnectReason::ProtocolError(ProtocolError {
+            code: error_code,
+            object_id: object_id.id,
+            object_interface: object_id.interface.name.into(),
+            message: converted_message,
+        }));
+    }
+
+    /// Notify the client of a protocol error it is about to be killed for
+    pub(crate) fn send_protocol_error(&mut self, error: &ProtocolError) {
+        // the error is not necessarily tied to a live object, fallback to the display
+        let object_id =
+            self.object_for_protocol_id(error.object_id).unwrap_or_else(|_| InnerObjectId {
+                id: 1,
+                interface: &WL_DISPLAY_INTERFACE,
+                client_id: self.id.clone(),
+                serial: 0,
+            });
+        let message = CString::new(error.message.replace('\0', "")).unwrap();
+        self.send_error(object_id, error.code, message);
+    }
+
+    fn send_error(&mut self, object_id: InnerObjectId, error_code: u32, message: CString) {
         // errors are ignored, as the client will be killed anyway
         let _ = self.send_event(
             message!(
@@ -278,7 +302,7 @@ impl<D> Client<D> {
                 },
                 0, // wl_display.error
                 [
-                    Argument::Object(ObjectId { id: object_id.clone() }),
+                    Argument::Object(ObjectId { id: object_id }),
                     Argument::Uint(error_code),
                     Argument::Str(Some(Box::new(message))),
                 ],
@@ -287,12 +311,6 @@ impl<D> Client<D> {
             None,
         );
         let _ = self.flush();
-        self.kill(DisconnectReason::ProtocolError(ProtocolError {
-            code: error_code,
-            object_id: object_id.id,
-            object_interface: object_id.interface.name.into(),
-            message: converted_message,
-        }));
     }
 
     #[cfg(any(target_os = "linux", target_os = "android"))]
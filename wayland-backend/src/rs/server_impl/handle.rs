@@ -431,6 +431,9 @@ impl<D> ErasedState for State<D> {
 
     fn kill_client(&mut self, client_id: InnerClientId, reason: DisconnectReason) {
         if let Ok(client) = self.clients.get_client_mut(client_id) {
+            if let DisconnectReason::ProtocolError(ref error) = reason {
+                client.send_protocol_error(error);
+            }
             client.kill(reason)
         }
     }
@@ -12,7 +12,7 @@ use rustix::net::{
     SendAncillaryBuffer, SendAncillaryMessage, SendFlags,
 };
 
-use crate::protocol::{ArgumentType, Message};
+use crate::protocol::{Argument, ArgumentType, Message};
 
 use super::wire::{parse_message, write_to_buffers, MessageParseError, MessageWriteError};
 
@@ -21,6 +21,12 @@ pub const MAX_FDS_OUT: usize = 28;
 /// Maximum number of bytes that can be sent in a single socket message
 pub const MAX_BYTES_OUT: usize = 4096;
 
+// rustix does not expose MSG_CTRUNC in its `RecvFlags`
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const MSG_CTRUNC: u32 = 0x8;
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+const MSG_CTRUNC: u32 = 0x20;
+
 /*
  * Socket
  */
@@ -47,8 +53,16 @@ impl Socket {
 
         if !fds.is_empty() {
             let iov = [IoSlice::new(bytes)];
-            let mut cmsg_space = vec![0; rustix::cmsg_space!(ScmRights(fds.len()))];
-            let mut cmsg_buffer = SendAncillaryBuffer::new(&mut cmsg_space);
+            // avoid allocating in the common case, `BufferedSocket` never sends more FDs than this
+            let mut cmsg_space = [0; rustix::cmsg_space!(ScmRights(MAX_FDS_OUT))];
+            let mut cmsg_vec;
+            let cmsg_space = if fds.len() <= MAX_FDS_OUT {
+                &mut cmsg_space[..]
+            } else {
+                cmsg_vec = vec![0; rustix::cmsg_space!(ScmRights(fds.len()))];
+                &mut cmsg_vec[..]
+            };
+            let mut cmsg_buffer = SendAncillaryBuffer::new(cmsg_space);
             let fds =
                 unsafe { slice::from_raw_parts(fds.as_ptr() as *const BorrowedFd, fds.len()) };
             cmsg_buffer.push(SendAncillaryMessage::ScmRights(fds));
@@ -69,17 +83,27 @@ impl Socket {
     /// The `buffer` slice should be at least `MAX_BYTES_OUT` long and the `fds`
     /// slice `MAX_FDS_OUT` long, otherwise some data of the received message may
     /// be lost.
+    ///
+    /// Errors with `EOVERFLOW` if the peer sent more than `MAX_FDS_OUT` FDs in a single
+    /// socket message. In that case the FDs that could be received are closed, as the
+    /// stream can no longer be parsed reliably.
     pub fn rcv_msg(&self, buffer: &mut [u8], fds: &mut VecDeque<OwnedFd>) -> IoResult<usize> {
         #[cfg(not(target_os = "macos"))]
         let flags = RecvFlags::DONTWAIT | RecvFlags::CMSG_CLOEXEC;
         #[cfg(target_os = "macos")]
         let flags = RecvFlags::DONTWAIT;
 
-        let mut cmsg_space = vec![0; rustix::cmsg_space!(ScmRights(MAX_FDS_OUT))];
+        let mut cmsg_space = [0; rustix::cmsg_space!(ScmRights(MAX_FDS_OUT))];
         let mut cmsg_buffer = RecvAncillaryBuffer::new(&mut cmsg_space);
         let mut iov = [IoSliceMut::new(buffer)];
         let msg = retry_on_intr(|| recvmsg(&self.stream, &mut iov[..], &mut cmsg_buffer, flags))?;
 
+        if msg.flags.bits() & MSG_CTRUNC != 0 {
+            // some FDs were discarded by the kernel, the ones that made it are closed
+            // when the ancillary buffer is dropped
+            return Err(rustix::io::Errno::OVERFLOW.into());
+        }
+
         let received_fds = cmsg_buffer
             .drain()
             .filter_map(|cmsg| match cmsg {
@@ -147,18 +171,24 @@ impl BufferedSocket {
     }
 
     /// Flush the contents of the outgoing buffer into the socket
+    ///
+    /// Keeps writing until the buffer is empty, a partial write is not considered a success.
+    /// If the socket cannot accept more data, `WouldBlock` is returned and the remaining data
+    /// stays in the buffer.
     pub fn flush(&mut self) -> IoResult<()> {
-        let written = {
-            let bytes = self.out_data.get_contents();
-            if bytes.is_empty() {
-                return Ok(());
-            }
-            self.socket.send_msg(bytes, &self.out_fds)?
-        };
-        self.out_data.offset(written);
-        self.out_data.move_to_front();
-        self.out_fds.clear();
-        Ok(())
+        loop {
+            let written = {
+                let bytes = self.out_data.get_contents();
+                if bytes.is_empty() {
+                    return Ok(());
+                }
+                self.socket.send_msg(bytes, &self.out_fds)?
+            };
+            self.out_data.offset(written);
+            self.out_data.move_to_front();
+            // the fds are sent along the first written byte
+            self.out_fds.clear();
+        }
     }
 
     // internal method
@@ -169,23 +199,42 @@ impl BufferedSocket {
     // if false is returned, it means there is not enough space
     // in the buffer
     fn attempt_write_message(&mut self, msg: &Message<u32, RawFd>) -> IoResult<bool> {
+        let pending_fds = self.out_fds.len();
         match write_to_buffers(msg, self.out_data.get_writable_storage(), &mut self.out_fds) {
             Ok(bytes_out) => {
                 self.out_data.advance(bytes_out);
                 Ok(true)
             }
-            Err(MessageWriteError::BufferTooSmall) => Ok(false),
-            Err(MessageWriteError::DupFdFailed(e)) => Err(e),
+            Err(e) => {
+                // drop the FDs that were dup()-ed for this partially written message,
+                // so that they are not sent along the next one
+                self.out_fds.truncate(pending_fds);
+                match e {
+                    MessageWriteError::BufferTooSmall => Ok(false),
+                    MessageWriteError::DupFdFailed(e) => Err(e),
+                }
+            }
         }
     }
 
     /// Write a message to the outgoing buffer
     ///
-    /// This method may flush the internal buffer if necessary (if it is full).
+    /// This method may flush the internal buffer if necessary (if it is full, or if
+    /// the message would bring the number of pending FDs above `MAX_FDS_OUT`).
     ///
     /// If the message is too big to fit in the buffer, the error `Error::Sys(E2BIG)`
     /// will be returned.
     pub fn write_message(&mut self, msg: &Message<u32, RawFd>) -> IoResult<()> {
+        let msg_fds = msg.args.iter().filter(|arg| matches!(arg, Argument::Fd(_))).count();
+        if self.out_fds.len() + msg_fds > MAX_FDS_OUT {
+            // the receiving end cannot accept more FDs than this in a single socket message
+            if let Err(e) = self.flush() {
+                // a partial write still sends the pending FDs, only fail if it did not
+                if e.kind() != ErrorKind::WouldBlock || self.out_fds.len() + msg_fds > MAX_FDS_OUT {
+                    return Err(e);
+                }
+            }
+        }
         if !self.attempt_write_message(msg)? {
             // the attempt failed, there is not enough space in the buffer
             // we need to flush it
@@ -450,6 +499,120 @@ mod tests {
         assert_eq_msgs(&msg.map_fd(|fd| fd.as_raw_fd()), &ret_msg.map_fd(IntoRawFd::into_raw_fd));
     }
 
+    #[test]
+    fn write_read_cycle_many_fds() {
+        // more FDs than can fit in a single socket message
+        let count = 2 * MAX_FDS_OUT + 3;
+        let msg = Message { sender_id: 42, opcode: 0, args: smallvec![Argument::Fd(1)] };
+
+        static SIGNATURE: &[ArgumentType] = &[ArgumentType::Fd];
+
+        let (client, server) = ::std::os::unix::net::UnixStream::pair().unwrap();
+        let mut client = BufferedSocket::new(Socket::from(client));
+        let mut server = BufferedSocket::new(Socket::from(server));
+
+        for _ in 0..count {
+            client.write_message(&msg).unwrap();
+        }
+        client.flush().unwrap();
+
+        let mut recv_msgs = Vec::new();
+        while recv_msgs.len() < count {
+            server.fill_incoming_buffers().unwrap();
+            while let Ok(message) = server.read_one_message(|_, _| Some(SIGNATURE)) {
+                recv_msgs.push(message);
+            }
+        }
+        assert_eq!(recv_msgs.len(), count);
+        for ret_msg in recv_msgs {
+            assert_eq_msgs(
+                &msg.clone().map_fd(|fd| fd.as_raw_fd()),
+                &ret_msg.map_fd(IntoRawFd::into_raw_fd),
+            );
+        }
+    }
+
+    #[test]
+    fn write_many_fds_partly_full_socket() {
+        let small = Message { sender_id: 42, opcode: 0, args: smallvec![] };
+        let with_fd = Message { sender_id: 42, opcode: 1, args: smallvec![Argument::Fd(1)] };
+
+        static SIGNATURE: &[ArgumentType] = &[ArgumentType::Fd];
+
+        let (client, server) = ::std::os::unix::net::UnixStream::pair().unwrap();
+        // use the smallest send buffer, so that a flush only fits part of the data
+        rustix::net::sockopt::set_socket_send_buffer_size(&client, 1).unwrap();
+        let mut client = BufferedSocket::new(Socket::from(client));
+        let mut server = BufferedSocket::new(Socket::from(server));
+
+        // fill the socket with one message per socket message, then have the peer
+        // read one of them to make a little room
+        let mut bytes = [0; 8];
+        let mut fds = Vec::new();
+        let len = write_to_buffers(&small, &mut bytes, &mut fds).unwrap();
+        let mut filled = 0;
+        while client.socket.send_msg(&bytes[..len], &[]).is_ok() {
+            filled += 1;
+        }
+        let mut in_fds = VecDeque::new();
+        server.socket.rcv_msg(&mut bytes, &mut in_fds).unwrap();
+        filled -= 1;
+
+        // a flush forced by the fd limit may only be partial, this must not be an error
+        for _ in 0..450 {
+            client.write_message(&small).unwrap();
+        }
+        for _ in 0..=MAX_FDS_OUT {
+            client.write_message(&with_fd).unwrap();
+        }
+
+        let (mut small_count, mut fd_count) = (0, 0);
+        while small_count < filled + 450 || fd_count <= MAX_FDS_OUT {
+            match client.flush() {
+                Err(e) if e.kind() == ErrorKind::WouldBlock => {}
+                ret => ret.unwrap(),
+            }
+            server.fill_incoming_buffers().unwrap();
+            while let Ok(message) =
+                server.read_one_message(
+                    |_, opcode| {
+                        if opcode == 1 {
+                            Some(SIGNATURE)
+                        } else {
+                            Some(&[])
+                        }
+                    },
+                )
+            {
+                if message.opcode == 1 {
+                    fd_count += 1;
+                } else {
+                    small_count += 1;
+                }
+            }
+        }
+        assert_eq!(small_count, filled + 450);
+        assert_eq!(fd_count, MAX_FDS_OUT + 1);
+    }
+
+    #[test]
+    fn rcv_msg_truncated_fds() {
+        let (client, server) = ::std::os::unix::net::UnixStream::pair().unwrap();
+        let client = Socket::from(client);
+        let server = Socket::from(server);
+
+        let fds = (0..2 * MAX_FDS_OUT)
+            .map(|_| rustix::io::dup(client.as_fd()).unwrap())
+            .collect::<Vec<_>>();
+        client.send_msg(&[0; 8], &fds).unwrap();
+
+        let mut buffer = [0; MAX_BYTES_OUT];
+        let mut in_fds = VecDeque::new();
+        let err = server.rcv_msg(&mut buffer, &mut in_fds).unwrap_err();
+        assert_eq!(err.raw_os_error(), Some(rustix::io::Errno::OVERFLOW.raw_os_error()));
+        assert!(in_fds.is_empty());
+    }
+
     #[test]
     fn write_read_cycle_multiple() {
         let messages = vec![
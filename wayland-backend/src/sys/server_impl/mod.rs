@@ -1198,6 +1198,37 @@ impl<D: 'static> ErasedState for State<D> {
         if !client_id.alive.load(Ordering::Acquire) {
             return;
         }
+        if let DisconnectReason::ProtocolError(ref error) = reason {
+            // notify the client of the error, using the display if the object does not exist
+            let mut resource = unsafe {
+                ffi_dispatch!(
+                    wayland_server_handle(),
+                    wl_client_get_object,
+                    client_id.ptr,
+                    error.object_id
+                )
+            };
+            if resource.is_null() {
+                resource = unsafe {
+                    ffi_dispatch!(wayland_server_handle(), wl_client_get_object, client_id.ptr, 1)
+                };
+            }
+            if !resource.is_null() {
+                // the message is used as a printf format string
+                let message =
+                    CString::new(error.message.replace('\0', "").replace('%', "%%")).unwrap();
+                unsafe {
+                    ffi_dispatch!(
+                        wayland_server_handle(),
+                        wl_resource_post_error,
+                        resource,
+                        error.code,
+                        message.as_ptr()
+                    );
+                    ffi_dispatch!(wayland_server_handle(), wl_client_flush, client_id.ptr);
+                }
+            }
+        }
         if let Some(udata) = unsafe { client_user_data(client_id.ptr) } {
             let udata = unsafe { &*udata };
             udata.alive.store(false, Ordering::Release);
@@ -8,6 +8,7 @@ use std::{
         atomic::{AtomicBool, Ordering},
         Arc,
     },
+    time::{Duration, Instant},
 };
 
 use wayland_backend::{
@@ -125,6 +126,30 @@ impl Connection {
         self.backend.flush()
     }
 
+    /// Flush pending outgoing events to the server, waiting for the socket to accept them
+    ///
+    /// Unlike [`flush()`](Connection::flush), if the socket buffer is full this method waits for the server
+    /// to read its incoming data, up to the provided `timeout`, until all pending requests have been written
+    /// to the socket. If the data could not be written before the timeout expires, an
+    /// [`io::Error`](std::io::Error) of kind [`TimedOut`](ErrorKind::TimedOut) is returned.
+    pub fn flush_timeout(&self, timeout: Duration) -> Result<(), WaylandError> {
+        // a timeout too large to be represented never expires
+        self.flush_until(Instant::now().checked_add(timeout))
+    }
+
+    pub(crate) fn flush_until(&self, deadline: Option<Instant>) -> Result<(), WaylandError> {
+        loop {
+            match self.backend.flush() {
+                Err(WaylandError::Io(e)) if e.kind() == ErrorKind::WouldBlock => {}
+                ret => return ret,
+            }
+            // wait for the server to make some room in the socket
+            let fd = self.backend.poll_fd();
+            let mut fds = [rustix::event::PollFd::new(&fd, rustix::event::PollFlags::OUT)];
+            wait_for_fds(&mut fds, deadline)?;
+        }
+    }
+
     /// Start a synchronized read from the socket
     ///
     /// This is needed if you plan to wait on readiness of the Wayland socket using an event loop. See
@@ -146,6 +171,23 @@ impl Connection {
     ///
     /// See [`EventQueue::roundtrip()`] for a version that includes the dispatching of the event queue.
     pub fn roundtrip(&self) -> Result<usize, WaylandError> {
+        self.roundtrip_until(None)
+    }
+
+    /// Do a roundtrip to the server, with a timeout
+    ///
+    /// This method is similar to [`roundtrip()`](Connection::roundtrip), but gives up if the server did not
+    /// answer before the provided `timeout` expires, in which case an [`io::Error`](std::io::Error) of
+    /// kind [`TimedOut`](ErrorKind::TimedOut) is returned. This allows your app to detect a stalled server
+    /// instead of freezing.
+    ///
+    /// See [`EventQueue::roundtrip_timeout()`] for a version that includes the dispatching of the event
+    /// queue.
+    pub fn roundtrip_timeout(&self, timeout: Duration) -> Result<usize, WaylandError> {
+        self.roundtrip_until(Instant::now().checked_add(timeout))
+    }
+
+    fn roundtrip_until(&self, deadline: Option<Instant>) -> Result<usize, WaylandError> {
         let done = Arc::new(SyncData::default());
         let display = self.display();
         self.send_request(
@@ -158,10 +200,15 @@ impl Connection {
         let mut dispatched = 0;
 
         loop {
-            self.backend.flush()?;
+            // the server may keep sending events without ever answering the sync
+            if deadline.map_or(false, |deadline| Instant::now() >= deadline) {
+                return Err(WaylandError::Io(ErrorKind::TimedOut.into()));
+            }
+
+            self.flush_until(deadline)?;
 
             if let Some(guard) = self.backend.prepare_read() {
-                dispatched += blocking_read(guard)?;
+                dispatched += blocking_read(guard, deadline)?;
             } else {
                 dispatched += self.backend.dispatch_inner_queue()?;
             }
@@ -216,20 +263,17 @@ impl Connection {
     }
 }
 
-pub(crate) fn blocking_read(guard: ReadEventsGuard) -> Result<usize, WaylandError> {
+pub(crate) fn blocking_read(
+    guard: ReadEventsGuard,
+    deadline: Option<Instant>,
+) -> Result<usize, WaylandError> {
     let fd = guard.connection_fd();
     let mut fds = [rustix::event::PollFd::new(
         &fd,
         rustix::event::PollFlags::IN | rustix::event::PollFlags::ERR,
     )];
 
-    loop {
-        match rustix::event::poll(&mut fds, -1) {
-            Ok(_) => break,
-            Err(rustix::io::Errno::INTR) => continue,
-            Err(e) => return Err(WaylandError::Io(e.into())),
-        }
-    }
+    wait_for_fds(&mut fds, deadline)?;
 
     // at this point the fd is ready
     match guard.read() {
@@ -240,6 +284,29 @@ pub(crate) fn blocking_read(guard: ReadEventsGuard) -> Result<usize, WaylandErro
     }
 }
 
+// Poll the fds until one of them is ready, or the deadline (if any) expires
+fn wait_for_fds(
+    fds: &mut [rustix::event::PollFd],
+    deadline: Option<Instant>,
+) -> Result<(), WaylandError> {
+    loop {
+        let timeout = match deadline {
+            Some(deadline) => {
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                // round up to the next millisecond to avoid spinning on sub-millisecond timeouts
+                ((remaining.as_nanos() + 999_999) / 1_000_000).min(i32::MAX as u128) as i32
+            }
+            None => -1,
+        };
+        match rustix::event::poll(fds, timeout) {
+            Ok(0) => return Err(WaylandError::Io(ErrorKind::TimedOut.into())),
+            Ok(_) => return Ok(()),
+            Err(rustix::io::Errno::INTR) => continue,
+            Err(e) => return Err(WaylandError::Io(e.into())),
+        }
+    }
+}
+
 /// An error when trying to establish a Wayland connection.
 #[derive(Debug)]
 pub enum ConnectError {
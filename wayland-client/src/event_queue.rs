@@ -1,10 +1,12 @@
 use std::any::Any;
 use std::collections::VecDeque;
 use std::convert::Infallible;
+use std::io::ErrorKind;
 use std::marker::PhantomData;
 use std::os::unix::io::{AsFd, BorrowedFd, OwnedFd};
 use std::sync::{atomic::Ordering, Arc, Condvar, Mutex};
 use std::task;
+use std::time::{Duration, Instant};
 
 use wayland_backend::{
     client::{Backend, ObjectData, ObjectId, ReadEventsGuard, WaylandError},
@@ -392,15 +394,23 @@ impl<State> EventQueue<State> {
     ///
     /// A simple app event loop can consist of invoking this method in a loop.
     pub fn blocking_dispatch(&mut self, data: &mut State) -> Result<usize, DispatchError> {
+        self.blocking_dispatch_until(data, None)
+    }
+
+    fn blocking_dispatch_until(
+        &mut self,
+        data: &mut State,
+        deadline: Option<Instant>,
+    ) -> Result<usize, DispatchError> {
         let dispatched = self.dispatch_pending(data)?;
         if dispatched > 0 {
             return Ok(dispatched);
         }
 
-        self.conn.flush()?;
+        self.conn.flush_until(deadline)?;
 
         if let Some(guard) = self.conn.prepare_read() {
-            crate::conn::blocking_read(guard)?;
+            crate::conn::blocking_read(guard, deadline)?;
         }
 
         self.dispatch_pending(data)
@@ -414,6 +424,28 @@ impl<State> EventQueue<State> {
     /// This function may be useful during initial setup of your app. This function may also be useful
     /// where you need to guarantee all requests prior to calling this function are completed.
     pub fn roundtrip(&mut self, data: &mut State) -> Result<usize, DispatchError> {
+        self.roundtrip_until(data, None)
+    }
+
+    /// Synchronous roundtrip, with a timeout
+    ///
+    /// This function is similar to [`roundtrip()`](EventQueue::roundtrip), but gives up if the server did
+    /// not answer before the provided `timeout` expires, in which case a [`DispatchError::Backend`]
+    /// containing an [`io::Error`](std::io::Error) of kind [`TimedOut`](std::io::ErrorKind::TimedOut) is
+    /// returned. Events received before the timeout expired are still dispatched.
+    pub fn roundtrip_timeout(
+        &mut self,
+        data: &mut State,
+        timeout: Duration,
+    ) -> Result<usize, DispatchError> {
+        self.roundtrip_until(data, Instant::now().checked_add(timeout))
+    }
+
+    fn roundtrip_until(
+        &mut self,
+        data: &mut State,
+        deadline: Option<Instant>,
+    ) -> Result<usize, DispatchError> {
         let done = Arc::new(SyncData::default());
 
         let display = self.conn.display();
@@ -428,7 +460,11 @@ impl<State> EventQueue<State> {
         let mut dispatched = 0;
 
         while !done.done.load(Ordering::Relaxed) {
-            dispatched += self.blocking_dispatch(data)?;
+            // the server may keep sending events without ever answering the sync
+            if deadline.map_or(false, |deadline| Instant::now() >= deadline) {
+                return Err(WaylandError::Io(ErrorKind::TimedOut.into()).into());
+            }
+            dispatched += self.blocking_dispatch_until(data, deadline)?;
         }
 
         Ok(dispatched)
@@ -128,9 +128,18 @@ pub(crate) fn gen_msg_constants(requests: &[Message], events: &[Message]) -> Tok
         let opcode_cstname = format_ident!("REQ_{}_OPCODE", msg.name.to_ascii_uppercase());
         let since = msg.since;
         let opcode = opcode as u16;
+        let deprecated = msg.deprecated_since.map(|deprecated_since| {
+            let deprecated_cstname =
+                format_ident!("REQ_{}_DEPRECATED_SINCE", msg.name.to_ascii_uppercase());
+            quote! {
+                /// The object version from which this request is deprecated
+                pub const #deprecated_cstname: u32 = #deprecated_since;
+            }
+        });
         quote! {
             /// The minimal object version supporting this request
             pub const #since_cstname: u32 = #since;
+            #deprecated
             /// The wire opcode for this request
             pub const #opcode_cstname: u16 = #opcode;
         }
@@ -140,9 +149,18 @@ pub(crate) fn gen_msg_constants(requests: &[Message], events: &[Message]) -> Tok
         let opcode_cstname = format_ident!("EVT_{}_OPCODE", msg.name.to_ascii_uppercase());
         let since = msg.since;
         let opcode = opcode as u16;
+        let deprecated = msg.deprecated_since.map(|deprecated_since| {
+            let deprecated_cstname =
+                format_ident!("EVT_{}_DEPRECATED_SINCE", msg.name.to_ascii_uppercase());
+            quote! {
+                /// The object version from which this event is deprecated
+                pub const #deprecated_cstname: u32 = #deprecated_since;
+            }
+        });
         quote! {
             /// The minimal object version supporting this event
             pub const #since_cstname: u32 = #since;
+            #deprecated
             /// The wire opcode for this event
             pub const #opcode_cstname: u16 = #opcode;
         }
@@ -179,6 +197,10 @@ pub(crate) fn gen_message_enum(
                 write!(docs, "\nOnly available since version {} of the interface", msg.since)
                     .unwrap();
             }
+            if let Some(deprecated_since) = msg.deprecated_since {
+                write!(docs, "\nDeprecated since version {} of the interface", deprecated_since)
+                    .unwrap();
+            }
 
             let doc_attr = to_doc_attr(&docs);
             let msg_name = Ident::new(&snake_to_camel(&msg.name), Span::call_site());
@@ -196,6 +196,7 @@ fn parse_request<R: BufRead>(reader: &mut Reader<R>, attrs: Attributes) -> Messa
             b"name" => request.name = decode_utf8_or_panic(attr.value.into_owned()),
             b"type" => request.typ = Some(parse_type(&attr.value)),
             b"since" => request.since = parse_or_panic(&attr.value),
+            b"deprecated-since" => request.deprecated_since = Some(parse_or_panic(&attr.value)),
             _ => {}
         }
     }
@@ -256,6 +257,7 @@ fn parse_event<R: BufRead>(reader: &mut Reader<R>, attrs: Attributes) -> Message
             b"name" => event.name = decode_utf8_or_panic(attr.value.into_owned()),
             b"type" => event.typ = Some(parse_type(&attr.value)),
             b"since" => event.since = parse_or_panic(&attr.value),
+            b"deprecated-since" => event.deprecated_since = Some(parse_or_panic(&attr.value)),
             _ => {}
         }
     }
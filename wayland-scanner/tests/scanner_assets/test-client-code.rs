@@ -762,6 +762,8 @@ pub mod test_global {
     pub const REQ_GET_TERTIARY_OPCODE: u16 = 2u16;
     #[doc = r" The minimal object version supporting this request"]
     pub const REQ_LINK_SINCE: u32 = 3u32;
+    #[doc = r" The object version from which this request is deprecated"]
+    pub const REQ_LINK_DEPRECATED_SINCE: u32 = 5u32;
     #[doc = r" The wire opcode for this request"]
     pub const REQ_LINK_OPCODE: u16 = 3u16;
     #[doc = r" The minimal object version supporting this request"]
@@ -810,7 +812,7 @@ pub mod test_global {
         GetSecondary {},
         #[doc = "Only available since version 3 of the interface"]
         GetTertiary {},
-        #[doc = "link a secondary and a tertiary\n\n\n\nOnly available since version 3 of the interface"]
+        #[doc = "link a secondary and a tertiary\n\n\n\nOnly available since version 3 of the interface\nDeprecated since version 5 of the interface"]
         Link { sec: super::secondary::Secondary, ter: Option<super::tertiary::Tertiary>, time: u32 },
         #[doc = "This is a destructor, once sent this object cannot be used any longer.\nOnly available since version 4 of the interface"]
         Destroy,
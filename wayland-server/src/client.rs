@@ -2,7 +2,7 @@ use std::sync::Arc;
 
 use wayland_backend::{
     protocol::ProtocolError,
-    server::{ClientData, ClientId, DisconnectReason, InvalidId, ObjectData},
+    server::{ClientData, ClientId, DisconnectReason, InvalidId, ObjectData, ObjectId},
 };
 
 use crate::{dispatch::ResourceData, Dispatch, DisplayHandle, Resource};
@@ -103,7 +103,35 @@ impl Client {
         I::from_id(handle, object_id)
     }
 
+    /// List the ids of all the objects owned by this client
+    ///
+    /// Fails if the client is no longer alive.
+    ///
+    /// With the `server_system` backend, objects that were not created through wayland-rs (such as the
+    /// `wl_display` and `wl_registry` objects, which are handled by libwayland) are not listed.
+    pub fn object_ids(&self, handle: &DisplayHandle) -> Result<Vec<ObjectId>, InvalidId> {
+        let mut ids = Vec::new();
+        handle.handle.with_all_objects_for(self.id.clone(), |id| ids.push(id))?;
+        Ok(ids)
+    }
+
+    /// List all the resources of interface `I` owned by this client
+    ///
+    /// Fails if the client is no longer alive.
+    pub fn resources<I: Resource + 'static>(
+        &self,
+        handle: &DisplayHandle,
+    ) -> Result<Vec<I>, InvalidId> {
+        Ok(self
+            .object_ids(handle)?
+            .into_iter()
+            .filter_map(|id| I::from_id(handle, id).ok())
+            .collect())
+    }
+
     /// Kill this client by triggering a protocol error
+    ///
+    /// The error is sent to the client as a `wl_display.error` event before its connection is closed.
     pub fn kill(&self, handle: &DisplayHandle, error: ProtocolError) {
         handle.handle.kill_client(self.id.clone(), DisconnectReason::ProtocolError(error))
     }
@@ -4,8 +4,9 @@ use std::{
     fs::{self, File},
     io,
     os::unix::{
+        ffi::OsStrExt,
         fs::OpenOptionsExt,
-        io::{AsFd, BorrowedFd},
+        io::{AsFd, BorrowedFd, FromRawFd, IntoRawFd, OwnedFd},
     },
     os::unix::{
         io::{AsRawFd, RawFd},
@@ -13,6 +14,7 @@ use std::{
         prelude::MetadataExt,
     },
     path::PathBuf,
+    sync::atomic::{AtomicBool, Ordering},
 };
 
 use rustix::fs::{flock, FlockOperation};
@@ -21,10 +23,11 @@ use rustix::fs::{flock, FlockOperation};
 #[derive(Debug)]
 pub struct ListeningSocket {
     listener: UnixListener,
-    _lock: File,
-    socket_path: PathBuf,
-    lock_path: PathBuf,
+    _lock: Option<File>,
+    socket_path: Option<PathBuf>,
+    lock_path: Option<PathBuf>,
     socket_name: Option<OsString>,
+    fd_name: Option<OsString>,
 }
 
 impl ListeningSocket {
@@ -137,7 +140,138 @@ impl ListeningSocket {
 
         listener.set_nonblocking(true).map_err(BindError::Io)?;
 
-        Ok(Self { listener, _lock, socket_path, lock_path, socket_name: None })
+        Ok(Self {
+            listener,
+            _lock: Some(_lock),
+            socket_path: Some(socket_path),
+            lock_path: Some(lock_path),
+            socket_name: None,
+            fd_name: None,
+        })
+    }
+
+    /// Create a listening socket from an already bound unix listener
+    ///
+    /// This can be used to listen on a socket that was not created by this type, for example one
+    /// inherited from a parent process. The listener will be set in non-blocking mode.
+    ///
+    /// No lockfile is acquired, and the socket file is not removed when this value is dropped, as it
+    /// is owned by whoever created it.
+    pub fn from_listener(listener: UnixListener) -> io::Result<Self> {
+        listener.set_nonblocking(true)?;
+        Ok(Self {
+            listener,
+            _lock: None,
+            socket_path: None,
+            lock_path: None,
+            socket_name: None,
+            fd_name: None,
+        })
+    }
+
+    /// Take the listening sockets passed to this process by systemd socket activation
+    ///
+    /// This reads the `LISTEN_PID`, `LISTEN_FDS` and `LISTEN_FDNAMES` environment variables. The sockets
+    /// are only taken by the first call, later calls return an empty list. File descriptors that are not
+    /// open or are not listening unix stream sockets are skipped and left untouched.
+    ///
+    /// If `unset_environment` is `true` the variables are removed from the environment, which is not
+    /// thread-safe on most platforms.
+    pub fn from_listen_fds(unset_environment: bool) -> io::Result<Vec<Self>> {
+        // the passed file descriptors are numbered sequentially after stdin, stdout and stderr
+        const LISTEN_FDS_START: RawFd = 3;
+        static TAKEN: AtomicBool = AtomicBool::new(false);
+
+        let pid = env::var("LISTEN_PID");
+        let fds = env::var("LISTEN_FDS");
+        let names = env::var_os("LISTEN_FDNAMES");
+        if unset_environment {
+            env::remove_var("LISTEN_PID");
+            env::remove_var("LISTEN_FDS");
+            env::remove_var("LISTEN_FDNAMES");
+        }
+
+        let (pid, fds) = match (pid, fds) {
+            (Ok(pid), Ok(fds)) => (pid, fds),
+            _ => return Ok(Vec::new()),
+        };
+        if pid.parse::<u32>().ok() != Some(std::process::id()) {
+            // the sockets were meant for another process
+            return Ok(Vec::new());
+        }
+        // the fds must all be below the limit of open files, reject anything else like `sd_listen_fds()`
+        let max_fds = rustix::process::getrlimit(rustix::process::Resource::Nofile)
+            .current
+            .unwrap_or(RawFd::MAX as u64);
+        let count: RawFd = fds
+            .parse()
+            .ok()
+            .filter(|&count| count >= 0 && (LISTEN_FDS_START as u64 + count as u64) <= max_fds)
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "Invalid LISTEN_FDS environment variable",
+                )
+            })?;
+        if TAKEN.swap(true, Ordering::AcqRel) {
+            return Ok(Vec::new());
+        }
+
+        let names: Vec<&OsStr> = names
+            .as_ref()
+            .map(|names| names.as_bytes().split(|&b| b == b':').map(OsStr::from_bytes).collect())
+            .unwrap_or_default();
+        let runtime_dir = env::var_os("XDG_RUNTIME_DIR").map(PathBuf::from);
+
+        let mut sockets = Vec::new();
+        for (i, fd) in (LISTEN_FDS_START..LISTEN_FDS_START + count).enumerate() {
+            // Safety: the fd is only borrowed for the duration of this call
+            if rustix::io::fcntl_getfd(unsafe { BorrowedFd::borrow_raw(fd) }).is_err() {
+                crate::log_warn!("Socket activation fd {} is not open, skipping it.", fd);
+                continue;
+            }
+            // Safety: the fd is open, it was passed to this process by the service manager, and the
+            // `TAKEN` flag ensures it is not taken a second time by this function
+            let listener = UnixListener::from(unsafe { OwnedFd::from_raw_fd(fd) });
+            let addr = match listener.local_addr() {
+                Ok(addr) if is_listening_stream(&listener) => addr,
+                _ => {
+                    // not a listening unix stream socket, leave it to whoever else may want it
+                    crate::log_warn!(
+                        "Socket activation fd {} is not a listening unix stream socket, skipping it.",
+                        fd
+                    );
+                    let _ = listener.into_raw_fd();
+                    continue;
+                }
+            };
+            let setup = rustix::io::fcntl_setfd(&listener, rustix::io::FdFlags::CLOEXEC)
+                .map_err(io::Error::from)
+                .and_then(|()| listener.set_nonblocking(true));
+            if let Err(e) = setup {
+                // don't lose the sockets taken so far, nor this one
+                crate::log_warn!(
+                    "Failed to set up socket activation fd {}: {}, skipping it.",
+                    fd,
+                    e
+                );
+                let _ = listener.into_raw_fd();
+                continue;
+            }
+            sockets.push(Self {
+                listener,
+                _lock: None,
+                socket_path: None,
+                lock_path: None,
+                socket_name: addr
+                    .as_pathname()
+                    .filter(|path| runtime_dir.is_some() && path.parent() == runtime_dir.as_deref())
+                    .and_then(|path| path.file_name())
+                    .map(Into::into),
+                fd_name: names.get(i).map(|&name| name.into()),
+            });
+        }
+        Ok(sockets)
     }
 
     /// Try to accept a new connection to the listening socket
@@ -154,11 +288,20 @@ impl ListeningSocket {
 
     /// Returns the name of the listening socket.
     ///
-    /// Will only be [`Some`] if that socket was created with [`bind`](ListeningSocket::bind) or
-    /// [`bind_auto`](ListeningSocket::bind_auto).
+    /// Will only be [`Some`] if that socket was created with [`bind`](ListeningSocket::bind),
+    /// [`bind_auto`](ListeningSocket::bind_auto), or [`from_listen_fds`](ListeningSocket::from_listen_fds)
+    /// for a socket located in `XDG_RUNTIME_DIR`.
     pub fn socket_name(&self) -> Option<&OsStr> {
         self.socket_name.as_deref()
     }
+
+    /// Returns the name given to this socket by the service manager.
+    ///
+    /// Will only be [`Some`] if that socket was created with
+    /// [`from_listen_fds`](ListeningSocket::from_listen_fds) and a name was set in `LISTEN_FDNAMES`.
+    pub fn fd_name(&self) -> Option<&OsStr> {
+        self.fd_name.as_deref()
+    }
 }
 
 impl AsRawFd for ListeningSocket {
@@ -187,10 +330,29 @@ impl AsFd for ListeningSocket {
     }
 }
 
+/// Check that a socket is a stream socket that is listening for connections, like
+/// `sd_is_socket_unix(fd, SOCK_STREAM, 1, ..)` does
+fn is_listening_stream(listener: &UnixListener) -> bool {
+    use rustix::net::{sockopt, SocketType};
+    if sockopt::get_socket_type(listener).ok() != Some(SocketType::STREAM) {
+        return false;
+    }
+    // Apple platforms do not implement SO_ACCEPTCONN
+    #[cfg(not(target_vendor = "apple"))]
+    if !sockopt::get_socket_acceptconn(listener).unwrap_or(false) {
+        return false;
+    }
+    true
+}
+
 impl Drop for ListeningSocket {
     fn drop(&mut self) {
-        let _ = fs::remove_file(&self.socket_path);
-        let _ = fs::remove_file(&self.lock_path);
+        if let Some(socket_path) = &self.socket_path {
+            let _ = fs::remove_file(socket_path);
+        }
+        if let Some(lock_path) = &self.lock_path {
+            let _ = fs::remove_file(lock_path);
+        }
     }
 }
 
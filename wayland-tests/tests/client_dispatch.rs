@@ -1,11 +1,17 @@
+#[macro_use]
 mod helpers;
 
 use helpers::*;
 
+use std::io::ErrorKind;
+use std::os::unix::net::UnixStream;
 use std::sync::{
     atomic::{AtomicBool, Ordering},
     Arc,
 };
+use std::time::{Duration, Instant};
+
+use ways::protocol::wl_output;
 
 #[test]
 fn client_roundtrip() {
@@ -30,3 +36,183 @@ fn client_roundtrip() {
 
     server_thread.join().unwrap();
 }
+
+#[test]
+fn client_roundtrip_timeout() {
+    let mut server = TestServer::<()>::new();
+
+    let (_, client) = server.add_client::<()>();
+
+    // the server never dispatches, so the roundtrip cannot complete
+    let err = client.conn.roundtrip_timeout(Duration::from_millis(50)).unwrap_err();
+    assert!(matches!(err, wayc::backend::WaylandError::Io(e) if e.kind() == ErrorKind::TimedOut));
+}
+
+#[test]
+fn client_roundtrip_timeout_max() {
+    let kill_switch = Arc::new(AtomicBool::new(false));
+    let server_kill_switch = kill_switch.clone();
+
+    let mut server = TestServer::new();
+
+    let (_, client) = server.add_client::<()>();
+
+    let server_thread = ::std::thread::spawn(move || loop {
+        server.display.dispatch_clients(&mut ()).unwrap();
+        server.display.flush_clients().unwrap();
+        if server_kill_switch.load(Ordering::Acquire) {
+            break;
+        }
+    });
+
+    // a timeout too large to be represented as a deadline never expires
+    client.conn.roundtrip_timeout(Duration::MAX).unwrap();
+
+    kill_switch.store(true, Ordering::Release);
+
+    server_thread.join().unwrap();
+}
+
+#[test]
+fn client_roundtrip_timeout_with_events() {
+    let kill_switch = Arc::new(AtomicBool::new(false));
+    let server_kill_switch = kill_switch.clone();
+
+    let mut server = TestServer::new();
+    server.display.handle().create_global::<ServerHandler, wl_output::WlOutput, _>(2, ());
+    let mut server_ddata = ServerHandler { outputs: Vec::new() };
+
+    let (_, mut client) = server.add_client();
+    let mut client_ddata = ClientHandler::new();
+
+    let registry = client.display.get_registry(&client.event_queue.handle(), ());
+
+    roundtrip(&mut client, &mut server, &mut client_ddata, &mut server_ddata).unwrap();
+
+    client_ddata
+        .globals
+        .bind::<wayc::protocol::wl_output::WlOutput, _, _>(
+            &client.event_queue.handle(),
+            &registry,
+            2..3,
+            (),
+        )
+        .unwrap();
+
+    roundtrip(&mut client, &mut server, &mut client_ddata, &mut server_ddata).unwrap();
+
+    let output = server_ddata.outputs.pop().unwrap();
+
+    // the server keeps sending events, but never answers the sync requests
+    let server_thread = ::std::thread::spawn(move || {
+        while !server_kill_switch.load(Ordering::Acquire) {
+            output.done();
+            server.display.flush_clients().unwrap();
+            ::std::thread::sleep(Duration::from_millis(1));
+        }
+    });
+
+    let start = Instant::now();
+
+    let err = client
+        .event_queue
+        .roundtrip_timeout(&mut client_ddata, Duration::from_millis(100))
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        wayc::DispatchError::Backend(wayc::backend::WaylandError::Io(e)) if e.kind() == ErrorKind::TimedOut
+    ));
+
+    let err = client.conn.roundtrip_timeout(Duration::from_millis(100)).unwrap_err();
+    assert!(matches!(err, wayc::backend::WaylandError::Io(e) if e.kind() == ErrorKind::TimedOut));
+
+    assert!(start.elapsed() < Duration::from_secs(1));
+
+    kill_switch.store(true, Ordering::Release);
+
+    server_thread.join().unwrap();
+}
+
+#[test]
+fn client_flush_timeout() {
+    let kill_switch = Arc::new(AtomicBool::new(false));
+    let server_kill_switch = kill_switch.clone();
+
+    let mut server = TestServer::<()>::new();
+
+    let (server_socket, client_socket) = UnixStream::pair().unwrap();
+    // make the socket buffer as small as possible, so that it can be filled
+    rustix::net::sockopt::set_socket_send_buffer_size(&client_socket, 1).unwrap();
+    server.display.handle().insert_client(server_socket, Arc::new(DumbClientData)).unwrap();
+    let client = TestClient::<ClientHandler>::new(client_socket);
+
+    // queue a bit more requests than fit in the client buffer, so that it
+    // fills the socket while leaving some requests pending
+    for _ in 0..400 {
+        client.display.sync(&client.event_queue.handle(), ());
+    }
+
+    // the server does not read, so the pending requests cannot be written
+    let err = client.conn.flush_timeout(Duration::from_millis(50)).unwrap_err();
+    assert!(matches!(err, wayc::backend::WaylandError::Io(e) if e.kind() == ErrorKind::TimedOut));
+
+    let server_thread = ::std::thread::spawn(move || loop {
+        server.display.dispatch_clients(&mut ()).unwrap();
+        server.display.flush_clients().unwrap();
+        if server_kill_switch.load(Ordering::Acquire) {
+            break;
+        }
+    });
+
+    // once the server reads, all the requests get written
+    client.conn.flush_timeout(Duration::from_secs(5)).unwrap();
+    client.conn.roundtrip_timeout(Duration::from_secs(5)).unwrap();
+
+    kill_switch.store(true, Ordering::Release);
+
+    server_thread.join().unwrap();
+}
+
+struct ClientHandler {
+    globals: globals::GlobalList,
+}
+
+impl ClientHandler {
+    fn new() -> ClientHandler {
+        ClientHandler { globals: Default::default() }
+    }
+}
+
+impl AsMut<globals::GlobalList> for ClientHandler {
+    fn as_mut(&mut self) -> &mut globals::GlobalList {
+        &mut self.globals
+    }
+}
+
+wayc::delegate_dispatch!(ClientHandler:
+    [wayc::protocol::wl_registry::WlRegistry: ()] => globals::GlobalList
+);
+
+client_ignore_impl!(ClientHandler => [
+    wayc::protocol::wl_output::WlOutput,
+    wayc::protocol::wl_callback::WlCallback
+]);
+
+struct ServerHandler {
+    outputs: Vec<wl_output::WlOutput>,
+}
+
+server_ignore_impl!(ServerHandler => [wl_output::WlOutput]);
+
+impl ways::GlobalDispatch<wl_output::WlOutput, ()> for ServerHandler {
+    fn bind(
+        state: &mut Self,
+        _: &ways::DisplayHandle,
+        _: &ways::Client,
+        output: ways::New<wl_output::WlOutput>,
+        _: &(),
+        data_init: &mut ways::DataInit<'_, Self>,
+    ) {
+        state.outputs.push(data_init.init(output, ()));
+    }
+}
@@ -57,6 +57,40 @@ fn client_receive_generic_error() {
     }
 }
 
+#[test]
+fn client_receive_error_on_kill() {
+    let mut server = TestServer::new();
+
+    let (s_client, mut client) = server.add_client();
+
+    let mut client_ddata = ClientHandler::new();
+
+    roundtrip(&mut client, &mut server, &mut client_ddata, &mut ServerHandler).unwrap();
+
+    // the server kills the client with a protocol error
+    s_client.kill(
+        &server.display.handle(),
+        ways::backend::protocol::ProtocolError {
+            code: 3,
+            object_id: 1,
+            object_interface: "wl_display".into(),
+            message: "I don't like you!".into(),
+        },
+    );
+
+    // the client connection may already be closed, so only read from it
+    assert!(client.conn.prepare_read().unwrap().read().is_err());
+    let error = client.conn.protocol_error().unwrap();
+    assert_eq!(error.code, 3);
+    assert_eq!(error.object_id, 1);
+    assert_eq!(error.object_interface, "wl_display");
+    // native lib can't give us the message
+    #[cfg(not(feature = "client_system"))]
+    {
+        assert_eq!(error.message, "I don't like you!");
+    }
+}
+
 struct ClientHandler {
     globals: globals::GlobalList,
 }
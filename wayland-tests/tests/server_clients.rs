@@ -1,8 +1,9 @@
 #[macro_use]
 mod helpers;
 
-use helpers::{globals, roundtrip, wayc, ways, TestServer};
+use helpers::{globals, roundtrip, wayc, ways, DumbClientData, TestClient, TestServer};
 
+use std::os::unix::net::{UnixListener, UnixStream};
 use std::sync::{
     atomic::{AtomicBool, Ordering},
     Arc,
@@ -80,6 +81,29 @@ fn client_credentials() {
     assert_credentials(credentials.unwrap());
 }
 
+#[test]
+fn listening_socket_from_listener() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("wayland-rs-test-from-listener");
+    let listening =
+        ways::ListeningSocket::from_listener(UnixListener::bind(&path).unwrap()).unwrap();
+    assert!(listening.socket_name().is_none());
+    // the listener was made non-blocking
+    assert!(listening.accept().unwrap().is_none());
+
+    let mut server = TestServer::new();
+    let mut client = TestClient::new(UnixStream::connect(&path).unwrap());
+    let stream = listening.accept().unwrap().unwrap();
+    server.display.handle().insert_client(stream, Arc::new(DumbClientData)).unwrap();
+
+    client.display.get_registry(&client.event_queue.handle(), ());
+    roundtrip(&mut client, &mut server, &mut ClientHandler::new(), &mut ServerHandler).unwrap();
+
+    // the socket file is not owned by the ListeningSocket
+    drop(listening);
+    assert!(path.exists());
+}
+
 #[cfg(any(not(feature = "server_system"), not(target_os = "freebsd")))]
 fn assert_credentials(credentials: ways::backend::Credentials) {
     assert!(credentials.pid != 0);
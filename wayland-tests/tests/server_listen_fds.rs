@@ -0,0 +1,75 @@
+use std::env;
+use std::mem::ManuallyDrop;
+use std::os::unix::io::{BorrowedFd, FromRawFd, OwnedFd};
+use std::os::unix::net::{UnixDatagram, UnixListener, UnixStream};
+
+use wayland_server::ListeningSocket;
+
+fn main() {
+    let runtime_dir = tempfile::tempdir().unwrap();
+    env::set_var("XDG_RUNTIME_DIR", runtime_dir.path());
+    let socket_path = runtime_dir.path().join("wayland-rs-test-listen-fds");
+
+    // place a unix listener on fd 3, a regular file on fd 4, a unix datagram socket on fd 5 and a
+    // connected unix stream on fd 6, like a service manager would
+    let listener = UnixListener::bind(&socket_path).unwrap();
+    let file = tempfile::tempfile().unwrap();
+    let (datagram, _) = UnixDatagram::pair().unwrap();
+    let (stream, _stream_peer) = UnixStream::pair().unwrap();
+    // move them out of the way first, in case they were themselves given fds 3 to 6
+    let listener = rustix::io::fcntl_dupfd_cloexec(listener, 10).unwrap();
+    let file = rustix::io::fcntl_dupfd_cloexec(file, 10).unwrap();
+    let datagram = rustix::io::fcntl_dupfd_cloexec(datagram, 10).unwrap();
+    let stream = rustix::io::fcntl_dupfd_cloexec(stream, 10).unwrap();
+    for (source, target) in [(listener, 3), (file, 4), (datagram, 5), (stream, 6)] {
+        // Safety: the target fd is never closed by this handle, it is only replaced by `dup2`
+        let mut target = ManuallyDrop::new(unsafe { OwnedFd::from_raw_fd(target) });
+        rustix::io::dup2(source, &mut target).unwrap();
+    }
+
+    // nothing is taken if the environment is not set
+    env::remove_var("LISTEN_PID");
+    env::remove_var("LISTEN_FDS");
+    env::remove_var("LISTEN_FDNAMES");
+    assert!(ListeningSocket::from_listen_fds(true).unwrap().is_empty());
+
+    // nothing is taken if the sockets were meant for another process
+    env::set_var("LISTEN_PID", (std::process::id() + 1).to_string());
+    env::set_var("LISTEN_FDS", "4");
+    env::set_var("LISTEN_FDNAMES", "wayland:file:datagram:stream");
+    assert!(ListeningSocket::from_listen_fds(false).unwrap().is_empty());
+    assert!(env::var_os("LISTEN_PID").is_some());
+
+    // an invalid number of fds is rejected
+    env::set_var("LISTEN_PID", std::process::id().to_string());
+    for count in ["-1", "2147483647", "wayland"] {
+        env::set_var("LISTEN_FDS", count);
+        assert!(ListeningSocket::from_listen_fds(false).is_err());
+    }
+
+    env::set_var("LISTEN_FDS", "4");
+    let sockets = ListeningSocket::from_listen_fds(true).unwrap();
+    assert!(env::var_os("LISTEN_PID").is_none());
+    assert!(env::var_os("LISTEN_FDS").is_none());
+    assert!(env::var_os("LISTEN_FDNAMES").is_none());
+
+    // the regular file and the sockets that are not listening streams are skipped and left open
+    assert_eq!(sockets.len(), 1);
+    for fd in 4..=6 {
+        let fd = unsafe { BorrowedFd::borrow_raw(fd) };
+        assert!(rustix::io::fcntl_getfd(fd).is_ok());
+    }
+
+    let socket = &sockets[0];
+    assert_eq!(socket.fd_name().unwrap(), "wayland");
+    assert_eq!(socket.socket_name().unwrap(), "wayland-rs-test-listen-fds");
+
+    // the socket accepts clients
+    let _client = UnixStream::connect(&socket_path).unwrap();
+    assert!(socket.accept().unwrap().is_some());
+
+    // the sockets are only taken once
+    env::set_var("LISTEN_PID", std::process::id().to_string());
+    env::set_var("LISTEN_FDS", "1");
+    assert!(ListeningSocket::from_listen_fds(true).unwrap().is_empty());
+}
@@ -199,6 +199,49 @@ fn get_resource() {
         .is_ok());
 }
 
+#[test]
+fn client_resources() {
+    let mut server = TestServer::new();
+    server
+        .display
+        .handle()
+        .create_global::<ServerHandler, ways::protocol::wl_output::WlOutput, _>(3, ());
+    let mut server_ddata = ServerHandler { outputs: Vec::new() };
+
+    let (s_client, mut client) = server.add_client();
+    let mut client_ddata = ClientHandler::new();
+
+    let registry = client.display.get_registry(&client.event_queue.handle(), ());
+
+    roundtrip(&mut client, &mut server, &mut client_ddata, &mut server_ddata).unwrap();
+
+    // create two outputs
+    for _ in 0..2 {
+        client_ddata
+            .globals
+            .bind::<wayc::protocol::wl_output::WlOutput, _, _>(
+                &client.event_queue.handle(),
+                &registry,
+                3..4,
+                (),
+            )
+            .unwrap();
+    }
+
+    roundtrip(&mut client, &mut server, &mut client_ddata, &mut server_ddata).unwrap();
+
+    let ids = s_client.object_ids(&server.display.handle()).unwrap();
+    assert!(server_ddata.outputs.iter().all(|output| ids.contains(&output.id())));
+
+    let outputs = s_client.resources::<wl_output::WlOutput>(&server.display.handle()).unwrap();
+    assert_eq!(outputs.len(), 2);
+    assert!(outputs.iter().all(|output| server_ddata.outputs.contains(output)));
+    assert!(s_client
+        .resources::<wl_compositor::WlCompositor>(&server.display.handle())
+        .unwrap()
+        .is_empty());
+}
+
 struct ClientHandler {
     globals: globals::GlobalList,
 }